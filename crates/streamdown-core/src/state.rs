@@ -0,0 +1,217 @@
+//! Streaming parse state machine.
+//!
+//! [`ParseState`] tracks the state needed to safely flush portions of a
+//! streaming markdown buffer before the whole document has arrived.
+
+/// Inline parsing sub-state. It holds no data: [`ParseState::find_flush_point`]
+/// tracks the balance of open inline markers itself, per call.
+#[derive(Debug, Clone, Default)]
+pub struct InlineState;
+
+/// Minimum buffer length (in chars) before a flush point is considered, so
+/// very short buffers aren't split eagerly.
+const MIN_FLUSH_LEN: usize = 60;
+
+/// The main state machine for streaming markdown parsing.
+#[derive(Debug, Clone, Default)]
+pub struct ParseState {
+    /// Whether the parser is currently inside a fenced code block.
+    pub in_fenced_code: bool,
+    /// Inline sub-state (holds no data; see [`InlineState`]).
+    pub inline: InlineState,
+}
+
+impl ParseState {
+    /// Create a fresh parse state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Find a safe point to flush a prefix of `buffer` for rendering.
+    ///
+    /// Returns `Some((ready, remainder))` where `ready` is a prefix safe to
+    /// render-and-commit immediately, and `remainder` is the rest of the
+    /// buffer that must wait for more input. Returns `None` when no safe
+    /// split point exists yet.
+    ///
+    /// A split point is safe when every inline marker (`**`, `*`/`_`,
+    /// backtick code spans, `[`...`]` links) opened since the start of the
+    /// buffer has already closed, and it falls right after a sentence
+    /// terminator: `.`, `,`, or `;` followed by whitespace, or a CJK
+    /// terminator (`ã€‚`, `ï¼Œ`, `ï¼›`). We refuse to split while inside a
+    /// fenced code block, when the buffer is shorter than
+    /// [`MIN_FLUSH_LEN`], or when it begins a structural block (`#`, `>`,
+    /// `|`), so headings, blockquotes, and tables are never cut mid-construction.
+    pub fn find_flush_point(&self, buffer: &str) -> Option<(String, String)> {
+        if self.in_fenced_code {
+            return None;
+        }
+        if buffer.chars().count() < MIN_FLUSH_LEN {
+            return None;
+        }
+        if matches!(buffer.trim_start().chars().next(), Some('#') | Some('>') | Some('|')) {
+            return None;
+        }
+
+        let chars: Vec<char> = buffer.chars().collect();
+        let mut stack: Vec<&'static str> = Vec::new();
+        let mut best_split: Option<usize> = None;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let ch = chars[i];
+
+            match ch {
+                '`' => {
+                    if stack.last() == Some(&"`") {
+                        stack.pop();
+                    } else {
+                        stack.push("`");
+                    }
+                    i += 1;
+                    continue;
+                }
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if stack.last() == Some(&"**") {
+                        stack.pop();
+                    } else {
+                        stack.push("**");
+                    }
+                    i += 2;
+                    continue;
+                }
+                '*' | '_' => {
+                    let token: &'static str = if ch == '*' { "*" } else { "_" };
+                    if stack.last() == Some(&token) {
+                        stack.pop();
+                    } else {
+                        stack.push(token);
+                    }
+                    i += 1;
+                    continue;
+                }
+                '[' => {
+                    stack.push("[");
+                    i += 1;
+                    continue;
+                }
+                ']' => {
+                    if stack.last() == Some(&"[") {
+                        stack.pop();
+                    }
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let is_cjk_terminator = matches!(ch, 'ã€‚' | 'ï¼Œ' | 'ï¼›');
+            let is_ascii_terminator = matches!(ch, '.' | ',' | ';');
+
+            if stack.is_empty() && (is_cjk_terminator || is_ascii_terminator) {
+                let next_is_whitespace = chars.get(i + 1).map(|c| c.is_whitespace()).unwrap_or(true);
+                if is_cjk_terminator || next_is_whitespace {
+                    best_split = Some(i + 1);
+                }
+            }
+
+            i += 1;
+        }
+
+        let split = best_split?;
+        let ready: String = chars[..split].iter().collect();
+        let remainder: String = chars[split..].iter().collect();
+
+        if ready.trim().is_empty() {
+            return None;
+        }
+
+        Some((ready, remainder))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_point_none_below_min_len() {
+        let state = ParseState::new();
+        assert_eq!(state.find_flush_point("Short. "), None);
+    }
+
+    #[test]
+    fn test_flush_point_splits_after_sentence() {
+        let state = ParseState::new();
+        let buffer = format!("{}. Next sentence is still being typed", "x".repeat(60));
+        let (ready, remainder) = state.find_flush_point(&buffer).unwrap();
+        assert!(ready.ends_with('.'));
+        assert_eq!(format!("{ready}{remainder}"), buffer);
+    }
+
+    #[test]
+    fn test_flush_point_cjk_terminator() {
+        let state = ParseState::new();
+        let buffer = format!("{}ã€‚è¿™æ˜¯è¿˜åœ¨è¾“å…¥çš„ä¸‹ä¸€å¥è¯", "å­—".repeat(60));
+        let (ready, _) = state.find_flush_point(&buffer).unwrap();
+        assert!(ready.ends_with('ã€‚'));
+    }
+
+    #[test]
+    fn test_flush_point_refuses_inside_fenced_code() {
+        let mut state = ParseState::new();
+        state.in_fenced_code = true;
+        let buffer = format!("{} Next.", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_refuses_heading() {
+        let state = ParseState::new();
+        let buffer = format!("# {} heading. more text", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_refuses_blockquote() {
+        let state = ParseState::new();
+        let buffer = format!("> {} quoted. more text", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_refuses_table() {
+        let state = ParseState::new();
+        let buffer = format!("| {} cell. more", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_waits_for_unbalanced_bold() {
+        let state = ParseState::new();
+        let buffer = format!("**{} still bold, not done", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_waits_for_unclosed_code_span() {
+        let state = ParseState::new();
+        let buffer = format!("`{} still in a code span. more", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_waits_for_unclosed_link() {
+        let state = ParseState::new();
+        let buffer = format!("[{} link text. more", "x".repeat(60));
+        assert_eq!(state.find_flush_point(&buffer), None);
+    }
+
+    #[test]
+    fn test_flush_point_splits_after_closed_bold() {
+        let state = ParseState::new();
+        let buffer = format!("**{}bold done**. More text is streaming in", "x".repeat(55));
+        let (ready, _) = state.find_flush_point(&buffer).unwrap();
+        assert!(ready.ends_with("**."));
+    }
+}
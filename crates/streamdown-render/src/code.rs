@@ -5,10 +5,13 @@
 //! - Pretty padding (â–„â–„â–„ / â–€â–€â–€ borders) or space-based borders
 //! - Line wrapping for long lines (optional)
 //! - Language labels
+//! - Line-number and diff-mark gutters (optional)
 
 use crate::{bg_color, fg_color, RenderStyle};
 use streamdown_ansi::codes::RESET;
 use streamdown_syntax::{HighlightState, Highlighter};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Characters for pretty code block borders.
 pub const CODEPAD_TOP: char = 'â–„'; // Lower half block
@@ -32,6 +35,45 @@ pub struct CodeBlockState<'a> {
     pub indent: usize,
     /// Accumulated raw code (for clipboard/savebrace)
     pub raw_buffer: String,
+    /// Right-edge marker rendered on a row that continues on the next row.
+    /// Empty disables the marker. Must be display-width 1.
+    pub wrapped_marker: String,
+    /// Left-edge prefix rendered on a continuation row. Empty disables the
+    /// prefix. Must be display-width 1.
+    pub continuation_prefix: String,
+    /// Maximum rows a single logical line may wrap into before the rest is
+    /// replaced by a truncation indicator row. `None` means unbounded.
+    pub max_wrapped_lines: Option<usize>,
+    /// Whether to render a 1-based line-number gutter.
+    pub show_line_numbers: bool,
+    /// Total number of lines in the block, used to size the line-number
+    /// gutter; read by each [`render_code_line`] call, so set it before
+    /// the first line is rendered.
+    pub total_lines: usize,
+    /// 1-based number of the next line [`render_code_line`] will render.
+    /// Reset to 1 by [`CodeBlockState::start`].
+    line_counter: usize,
+}
+
+/// A per-line diff annotation shown in the code-block gutter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMark {
+    /// Line added relative to the comparison.
+    Added,
+    /// Line removed relative to the comparison.
+    Removed,
+    /// Line modified relative to the comparison.
+    Modified,
+}
+
+impl DiffMark {
+    fn symbol(self) -> char {
+        match self {
+            DiffMark::Added => '+',
+            DiffMark::Removed => '-',
+            DiffMark::Modified => '~',
+        }
+    }
 }
 
 impl<'a> CodeBlockState<'a> {
@@ -46,14 +88,43 @@ impl<'a> CodeBlockState<'a> {
             pretty_broken: false,
             indent: 0,
             raw_buffer: String::new(),
+            wrapped_marker: "â†µ".to_string(),
+            continuation_prefix: "â†ª".to_string(),
+            max_wrapped_lines: None,
+            show_line_numbers: false,
+            total_lines: 0,
+            line_counter: 1,
+        }
+    }
+
+    /// Set the wrap continuation markers.
+    ///
+    /// Returns `false` (and leaves the previous markers unchanged) if
+    /// either symbol is not exactly one display column wide, since
+    /// `render_code_line`'s padding math assumes single-column gutter
+    /// symbols. Pass an empty string to disable a marker.
+    pub fn set_wrap_symbols(
+        &mut self,
+        wrapped_marker: impl Into<String>,
+        continuation_prefix: impl Into<String>,
+    ) -> bool {
+        let wrapped_marker = wrapped_marker.into();
+        let continuation_prefix = continuation_prefix.into();
+        let valid = |s: &str| s.is_empty() || UnicodeWidthStr::width(s) == 1;
+        if !valid(&wrapped_marker) || !valid(&continuation_prefix) {
+            return false;
         }
+        self.wrapped_marker = wrapped_marker;
+        self.continuation_prefix = continuation_prefix;
+        true
     }
 
     /// Start a new code block.
     pub fn start(&mut self, language: Option<String>, style: &RenderStyle) {
         self.language = language.clone();
-        self.background = bg_color(&style.dark);
+        self.background = bg_color(&style.dark, style.depth, style.theme);
         self.raw_buffer.clear();
+        self.line_counter = 1;
 
         // Create highlight state for the language
         let lang = language.as_deref().unwrap_or("text");
@@ -99,8 +170,8 @@ pub fn render_code_start(
     pretty_pad: bool,
 ) -> Vec<String> {
     let mut lines = Vec::new();
-    let bg = bg_color(&style.dark);
-    let fg = fg_color(&style.grey);
+    let bg = bg_color(&style.dark, style.depth, style.theme);
+    let fg = fg_color(&style.grey, style.depth, style.theme);
 
     if pretty_pad {
         // Pretty top border: â–„â–„â–„â–„â–„ (foreground color on dark background)
@@ -120,7 +191,7 @@ pub fn render_code_start(
     // Language label if provided
     if let Some(lang) = language {
         if !lang.is_empty() && lang != "text" {
-            let label_fg = fg_color(&style.symbol);
+            let label_fg = fg_color(&style.symbol, style.depth, style.theme);
             let lang_width = unicode_width::UnicodeWidthStr::width(lang);
             let padding = width.saturating_sub(lang_width + 2);
             lines.push(format!(
@@ -148,6 +219,19 @@ pub fn render_code_start(
 /// * `style` - Render style
 /// * `pretty_broken` - Whether to wrap long lines
 ///
+/// * `diff_mark` - Optional per-line diff annotation shown in the gutter
+///
+/// Wrapped rows are decorated using `state.wrapped_marker`/
+/// `state.continuation_prefix` (see [`CodeBlockState::set_wrap_symbols`])
+/// and capped at `state.max_wrapped_lines`, past which the remaining rows
+/// are replaced by a single truncation indicator row. When
+/// `state.show_line_numbers` is set, a right-aligned line-number and
+/// diff-mark gutter (sized from `state.total_lines`) is rendered between
+/// `left_margin` and the highlighted code, using `style.gutter_bg`/
+/// `style.gutter_fg`; wrapped continuation rows get a blank gutter so
+/// numbers stay aligned with logical lines. These decorations live only
+/// in the rendered output, never in `state.raw_buffer`.
+///
 /// # Returns
 /// Vector of rendered lines (may be multiple if wrapped)
 pub fn render_code_line(
@@ -157,15 +241,33 @@ pub fn render_code_line(
     left_margin: &str,
     style: &RenderStyle,
     pretty_broken: bool,
+    diff_mark: Option<DiffMark>,
 ) -> Vec<String> {
-    let bg = bg_color(&style.dark);
+    let bg = bg_color(&style.dark, style.depth, style.theme);
+    let gutter_bg = bg_color(&style.gutter_bg, style.depth, style.theme);
+    let gutter_fg = fg_color(&style.gutter_fg, style.depth, style.theme);
+
+    // The line-number/diff gutter sits between `left_margin` and the code,
+    // so it eats into the width available for the highlighted content.
+    let line_number_digits = state.total_lines.max(state.line_counter).max(1).to_string().len();
+    let line_gutter_width = if state.show_line_numbers {
+        line_number_digits + 2 // number + mark + separator space
+    } else {
+        0
+    };
+    let current_line_number = state.line_counter;
+    state.line_counter += 1;
+    let content_width = width.saturating_sub(line_gutter_width);
 
     // Wrap long lines if pretty_broken is enabled
-    let (indent, wrapped_lines) = code_wrap(line, width, pretty_broken);
+    let (indent, wrapped_lines) = code_wrap(line, content_width, pretty_broken);
+    let total = wrapped_lines.len();
+    let take_n = state.max_wrapped_lines.map(|max| max.min(total)).unwrap_or(total);
+    let truncated_rows = total - take_n;
 
     let mut result = Vec::new();
 
-    for (i, code_line) in wrapped_lines.iter().enumerate() {
+    for (i, code_line) in wrapped_lines.iter().take(take_n).enumerate() {
         // Highlight the line
         let highlighted = if let Some(ref mut hl_state) = state.highlight_state {
             state
@@ -175,32 +277,104 @@ pub fn render_code_line(
             code_line.to_string()
         };
 
-        // Calculate padding
-        let line_indent = if i == 0 { 0 } else { indent };
-        let indent_str = " ".repeat(line_indent);
+        // Line-number/diff gutter: blank on wrapped continuation rows so
+        // numbers stay aligned with logical lines.
+        let line_gutter = if !state.show_line_numbers {
+            String::new()
+        } else if i == 0 {
+            let mark = diff_mark.map(DiffMark::symbol).unwrap_or(' ');
+            format!(
+                "{gutter_bg}{gutter_fg}{current_line_number:>line_number_digits$}{mark} {bg}"
+            )
+        } else {
+            format!("{gutter_bg}{}{bg}", " ".repeat(line_gutter_width))
+        };
+
+        // Calculate the wrap-indent gutter: continuation rows get a blank
+        // or marked prefix so wrapped text still lines up under the indent.
+        let is_continuation = i > 0;
+        let has_prefix = is_continuation && !state.continuation_prefix.is_empty();
+        let indent_width = if !is_continuation {
+            0
+        } else if has_prefix {
+            indent.max(1)
+        } else {
+            indent
+        };
+        let mut indent_gutter = String::new();
+        if is_continuation {
+            if has_prefix {
+                indent_gutter.push_str(&state.continuation_prefix);
+                if indent_width > 1 {
+                    indent_gutter.push_str(&" ".repeat(indent_width - 1));
+                }
+            } else {
+                indent_gutter.push_str(&" ".repeat(indent_width));
+            }
+        }
+
+        // A right-edge marker indicates this row continues on the next one.
+        let has_more = i + 1 < total;
+        let marker = if has_more && !state.wrapped_marker.is_empty() {
+            state.wrapped_marker.as_str()
+        } else {
+            ""
+        };
+        let marker_width = if marker.is_empty() { 0 } else { 1 };
 
         // Build the line with background
-        let visible_len = streamdown_ansi::utils::visible_length(&highlighted) + line_indent;
-        let padding = width.saturating_sub(visible_len);
+        let visible_len =
+            streamdown_ansi::utils::visible_length(&highlighted) + indent_width + marker_width;
+        let padding = content_width.saturating_sub(visible_len);
 
         result.push(format!(
-            "{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}",
             left_margin,
+            line_gutter,
             bg,
-            indent_str,
+            indent_gutter,
             highlighted,
             " ".repeat(padding),
+            marker,
+            RESET
+        ));
+    }
+
+    if truncated_rows > 0 {
+        let symbol_fg = fg_color(&style.symbol, style.depth, style.theme);
+        let blank_line_gutter = if state.show_line_numbers {
+            format!("{gutter_bg}{}{bg}", " ".repeat(line_gutter_width))
+        } else {
+            String::new()
+        };
+        let message = format!("â‹¯ {} more wrapped line(s) truncated â‹¯", truncated_rows);
+        let message_width = unicode_width::UnicodeWidthStr::width(message.as_str());
+        let padding = content_width.saturating_sub(message_width);
+        result.push(format!(
+            "{}{}{}{}{}{}{}",
+            left_margin,
+            blank_line_gutter,
+            bg,
+            symbol_fg,
+            message,
+            " ".repeat(padding),
             RESET
         ));
     }
 
     if result.is_empty() {
         // Empty line - still show background
+        let blank_line_gutter = if state.show_line_numbers {
+            format!("{gutter_bg}{}{bg}", " ".repeat(line_gutter_width))
+        } else {
+            String::new()
+        };
         result.push(format!(
-            "{}{}{}{}",
+            "{}{}{}{}{}",
             left_margin,
+            blank_line_gutter,
             bg,
-            " ".repeat(width),
+            " ".repeat(content_width),
             RESET
         ));
     }
@@ -222,8 +396,8 @@ pub fn render_code_end(
     pretty_pad: bool,
 ) -> Vec<String> {
     let mut lines = Vec::new();
-    let bg = bg_color(&style.dark);
-    let fg = fg_color(&style.grey);
+    let bg = bg_color(&style.dark, style.depth, style.theme);
+    let fg = fg_color(&style.grey, style.depth, style.theme);
 
     if pretty_pad {
         // Pretty bottom border: â–€â–€â–€â–€â–€
@@ -246,15 +420,23 @@ pub fn render_code_end(
 /// Wrap a code line if it exceeds the width.
 ///
 /// Unlike text wrapping, code wrapping preserves indentation
-/// and doesn't break on word boundaries.
+/// and doesn't break on word boundaries. Wrapping operates on grapheme
+/// clusters and terminal display width (not `chars()`/byte length), so
+/// wide CJK/emoji characters are accounted for correctly and ZWJ
+/// sequences, flag pairs, and skin-tone modifiers are never split
+/// mid-cluster.
 ///
 /// # Arguments
 /// * `text` - The code line
-/// * `width` - Maximum width (in characters, not bytes)
+/// * `width` - Maximum width (in terminal display columns)
 /// * `pretty_broken` - If false, don't wrap (let terminal handle it)
 ///
 /// # Returns
-/// (indent, lines) - The detected indent and wrapped lines
+/// (indent, lines) - The detected indent (in display columns) and wrapped
+/// lines. When the line is wrapped, each emitted line is at most `width`
+/// display columns wide. The line is returned unwrapped, whatever its
+/// width, when `width` leaves no column past the indent and a 4-column
+/// reserve, or when it holds only whitespace.
 pub fn code_wrap(text: &str, width: usize, pretty_broken: bool) -> (usize, Vec<String>) {
     if text.is_empty() {
         return (0, vec![String::new()]);
@@ -266,8 +448,10 @@ pub fn code_wrap(text: &str, width: usize, pretty_broken: bool) -> (usize, Vec<S
         return (0, vec![text.to_string()]);
     }
 
-    // Detect indentation (count leading whitespace characters, not bytes)
-    let indent = text.chars().take_while(|c| c.is_whitespace()).count();
+    // Detect indentation as display columns of leading whitespace.
+    let leading_ws_len = text.chars().take_while(|c| c.is_whitespace()).count();
+    let leading_ws: String = text.chars().take(leading_ws_len).collect();
+    let indent = UnicodeWidthStr::width(leading_ws.as_str());
     let content = text.trim_start();
 
     if content.is_empty() {
@@ -276,30 +460,39 @@ pub fn code_wrap(text: &str, width: usize, pretty_broken: bool) -> (usize, Vec<S
 
     // Calculate effective width (accounting for indent on continuation lines)
     let effective_width = width.saturating_sub(4).saturating_sub(indent);
-    let content_char_count = content.chars().count();
+    let content_width = UnicodeWidthStr::width(content);
 
-    if effective_width == 0 || content_char_count <= effective_width {
+    if effective_width == 0 || content_width <= effective_width {
         return (indent, vec![text.to_string()]);
     }
 
-    // Wrap the content using character indices (not byte indices)
-    // This is critical for UTF-8 safety with multi-byte characters
+    // Wrap on grapheme cluster boundaries, accumulating display width. This
+    // is critical both for UTF-8 safety and to never bisect a ZWJ sequence,
+    // flag pair, or skin-tone modifier mid-cluster.
     let mut lines = Vec::new();
-    let chars: Vec<char> = content.chars().collect();
-    let mut start = 0;
+    let mut current = String::new();
+    let mut current_width = 0usize;
 
-    while start < chars.len() {
-        let end = (start + effective_width).min(chars.len());
-        let line: String = chars[start..end].iter().collect();
+    for grapheme in content.graphemes(true) {
+        let grapheme_width = UnicodeWidthStr::width(grapheme).max(1);
 
-        if start == 0 {
-            // First line includes original indentation
-            lines.push(format!("{}{}", " ".repeat(indent), line));
-        } else {
-            lines.push(line);
+        if current_width + grapheme_width > effective_width && !current.is_empty() {
+            lines.push(current);
+            current = String::new();
+            current_width = 0;
         }
 
-        start = end;
+        current.push_str(grapheme);
+        current_width += grapheme_width;
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    // First line includes original indentation
+    if let Some(first) = lines.first_mut() {
+        *first = format!("{}{}", " ".repeat(indent), first);
     }
 
     // Remove trailing empty lines
@@ -404,6 +597,102 @@ mod tests {
         assert_eq!(state.raw_code(), "fn main() {\n    println!(\"Hello\");\n}");
     }
 
+    #[test]
+    fn test_set_wrap_symbols_rejects_wide_markers() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+
+        assert!(!state.set_wrap_symbols("ã€€", "â†ª")); // fullwidth space is 2 columns wide
+        assert_eq!(state.continuation_prefix, "â†ª"); // unchanged on rejection
+        assert!(state.set_wrap_symbols(">", ""));
+        assert_eq!(state.wrapped_marker, ">");
+        assert_eq!(state.continuation_prefix, "");
+    }
+
+    #[test]
+    fn test_render_code_line_continuation_markers_not_in_raw_buffer() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+        let style = default_style();
+        state.start(Some("text".to_string()), &style);
+
+        let long_line = "x".repeat(100);
+        let lines = render_code_line(&long_line, &mut state, 40, "", &style, true, None);
+
+        assert!(lines.len() > 1);
+        assert!(lines[0].contains(&state.wrapped_marker));
+        assert!(lines[1].contains(&state.continuation_prefix));
+
+        state.add_raw_line(&long_line);
+        assert!(!state.raw_code().contains(&state.wrapped_marker));
+        assert!(!state.raw_code().contains(&state.continuation_prefix));
+    }
+
+    #[test]
+    fn test_render_code_line_max_wrapped_lines_truncates() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+        let style = default_style();
+        state.start(Some("text".to_string()), &style);
+        state.max_wrapped_lines = Some(2);
+
+        let long_line = "x".repeat(200);
+        let lines = render_code_line(&long_line, &mut state, 40, "", &style, true, None);
+
+        // 2 content rows + 1 truncation indicator row
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].contains("truncated"));
+    }
+
+    #[test]
+    fn test_render_code_line_shows_line_number_and_diff_mark() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+        let style = default_style();
+        state.start(Some("text".to_string()), &style);
+        state.show_line_numbers = true;
+        state.total_lines = 42;
+
+        let lines = render_code_line("let x = 1;", &mut state, 40, "", &style, false, Some(DiffMark::Added));
+
+        assert_eq!(lines.len(), 1);
+        let visible = streamdown_ansi::utils::visible(&lines[0]);
+        assert!(visible.starts_with(" 1+"));
+    }
+
+    #[test]
+    fn test_render_code_line_increments_line_counter() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+        let style = default_style();
+        state.start(Some("text".to_string()), &style);
+        state.show_line_numbers = true;
+        state.total_lines = 3;
+
+        let first = render_code_line("a", &mut state, 40, "", &style, false, None);
+        let second = render_code_line("b", &mut state, 40, "", &style, false, None);
+
+        assert!(streamdown_ansi::utils::visible(&first[0]).starts_with("1 "));
+        assert!(streamdown_ansi::utils::visible(&second[0]).starts_with("2 "));
+    }
+
+    #[test]
+    fn test_render_code_line_continuation_has_blank_number_gutter() {
+        let highlighter = Highlighter::new();
+        let mut state = CodeBlockState::new(&highlighter);
+        let style = default_style();
+        state.start(Some("text".to_string()), &style);
+        state.show_line_numbers = true;
+        state.total_lines = 1;
+
+        let long_line = "x".repeat(100);
+        let lines = render_code_line(&long_line, &mut state, 40, "", &style, true, None);
+
+        assert!(lines.len() > 1);
+        let visible = streamdown_ansi::utils::visible(&lines[1]);
+        assert!(visible.starts_with("  ")); // blank gutter, not a line number
+    }
+
     #[test]
     fn test_code_wrap_multibyte_utf8_characters() {
         // 'â•' is 3 bytes (U+2550). Buggy byte-based slicing at position 36 would
@@ -420,18 +709,21 @@ mod tests {
 
     #[test]
     fn test_code_wrap_multibyte_indent_detection() {
-        // 'ã€€' (fullwidth space, U+3000) is 3 bytes. Indent should be 2 chars, not 6 bytes.
+        // 'ã€€' (fullwidth space, U+3000) is 3 bytes and 2 display columns wide.
+        // Indent should be 4 display columns, not 6 bytes or 2 chars.
         let line = "ã€€ã€€code";
 
         let (indent, _) = code_wrap(line, 80, true);
 
-        assert_eq!(indent, 2);
+        assert_eq!(indent, 4);
     }
 
     #[test]
     fn test_code_wrap_emoji_byte_boundary() {
-        // ðŸŽ‰ is 4 bytes. With width=20 (effective=16), buggy code slices at byte 16,
-        // which is inside the 4th emoji, causing a panic.
+        // ðŸŽ‰ is 4 bytes and 2 display columns wide. With width=20 (effective=16),
+        // byte- or char-based slicing either panics on the boundary or
+        // overflows the visible width; every emitted line must stay within
+        // the effective display width and no emoji may be split.
         let line = "xðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰ðŸŽ‰";
         assert_eq!(line.len(), 81);
         assert_eq!(line.chars().count(), 21);
@@ -439,9 +731,14 @@ mod tests {
         let (indent, lines) = code_wrap(line, 20, true);
 
         assert_eq!(indent, 0);
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0].chars().count(), 16);
-        assert_eq!(lines[1].chars().count(), 5);
+        assert!(lines.len() >= 2);
+        for emitted in &lines {
+            assert!(UnicodeWidthStr::width(emitted.as_str()) <= 16);
+        }
+        assert_eq!(
+            lines.iter().flat_map(|l| l.graphemes(true)).count(),
+            line.graphemes(true).count()
+        );
     }
 
     #[test]
@@ -491,7 +788,23 @@ mod tests {
         let (_, lines) = code_wrap(&line, 20, true);
 
         assert!(lines.len() >= 3);
-        assert_eq!(lines[0].chars().count(), 16);
+        for emitted in &lines {
+            assert!(UnicodeWidthStr::width(emitted.as_str()) <= 16);
+        }
+    }
+
+    #[test]
+    fn test_code_wrap_cjk_display_width() {
+        // Each CJK character is 2 display columns wide; char-based wrapping
+        // would fit twice as many per line as the terminal actually allows.
+        let line = "æ—¥".repeat(30);
+
+        let (_, lines) = code_wrap(&line, 20, true);
+
+        assert!(lines.len() >= 2);
+        for emitted in &lines {
+            assert!(UnicodeWidthStr::width(emitted.as_str()) <= 16);
+        }
     }
 
     #[test]
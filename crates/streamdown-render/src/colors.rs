@@ -38,6 +38,284 @@ pub fn resolve_color(color: &str) -> &str {
     COLODORE.get(color).copied().unwrap_or(color)
 }
 
+/// Light-terminal variant of [`COLODORE`].
+///
+/// Generated by flipping each preset's lightness in HSL space while
+/// keeping hue and saturation fixed, so chromatic presets stay their own
+/// hue (green stays green, just darker/lighter as needed for contrast on
+/// a light background) instead of flipping to their complementary color.
+pub static COLODORE_LIGHT: LazyLock<HashMap<&'static str, String>> = LazyLock::new(|| {
+    COLODORE
+        .iter()
+        .map(|(&name, &hex)| (name, invert_hex(hex)))
+        .collect()
+});
+
+/// Invert a hex color's lightness while preserving hue and saturation.
+fn invert_hex(hex: &str) -> String {
+    let (r, g, b) = parse_hex(hex);
+    let (h, s, l) = rgb_to_hsl(r, g, b);
+    let (r, g, b) = hsl_to_rgb(h, s, 1.0 - l);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Convert 8-bit RGB to HSL, with `h` in `[0, 360)` and `s`/`l` in `[0, 1]`.
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let r = r as f64 / 255.0;
+    let g = g as f64 / 255.0;
+    let b = b as f64 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+
+    if delta == 0.0 {
+        return (0.0, 0.0, l);
+    }
+
+    let s = if l < 0.5 {
+        delta / (max + min)
+    } else {
+        delta / (2.0 - max - min)
+    };
+    let h = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    } * 60.0;
+
+    (h, s, l)
+}
+
+/// Convert HSL (`h` in `[0, 360)`, `s`/`l` in `[0, 1]`) to 8-bit RGB.
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return (v, v, v);
+    }
+
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r1, g1, b1) = match h as u32 {
+        0..=59 => (c, x, 0.0),
+        60..=119 => (x, c, 0.0),
+        120..=179 => (0.0, c, x),
+        180..=239 => (0.0, x, c),
+        240..=299 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (
+        ((r1 + m) * 255.0).round() as u8,
+        ((g1 + m) * 255.0).round() as u8,
+        ((b1 + m) * 255.0).round() as u8,
+    )
+}
+
+/// Which terminal background the renderer is adapting its palette to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeMode {
+    /// Dark terminal background - the original Colodore palette.
+    Dark,
+    /// Light terminal background - [`COLODORE_LIGHT`].
+    Light,
+}
+
+/// Resolve a color string to hex for the given theme.
+///
+/// Colodore preset names are looked up in the palette for `mode`. Hex
+/// passthrough values are inverted for [`ThemeMode::Light`] so arbitrary
+/// user-supplied colors also flip consistently with the theme.
+pub fn resolve_color_themed(color: &str, mode: ThemeMode) -> String {
+    match mode {
+        ThemeMode::Dark => resolve_color(color).to_string(),
+        ThemeMode::Light => COLODORE_LIGHT
+            .get(color)
+            .cloned()
+            .unwrap_or_else(|| invert_hex(color)),
+    }
+}
+
+/// Detect the active [`ThemeMode`] from environment hints.
+///
+/// Checks the `COLORFGBG` environment variable (set by many terminals as
+/// `"fg;bg"` palette indices) and falls back to `osc11_response` - the
+/// terminal's OSC 11 background-color query reply, if the caller already
+/// queried and captured one (e.g. `"rgb:ffff/ffff/ffff"`). Defaults to
+/// [`ThemeMode::Dark`] when neither hint is available.
+pub fn detect_theme_mode(osc11_response: Option<&str>) -> ThemeMode {
+    if let Some(mode) = theme_from_colorfgbg(std::env::var("COLORFGBG").ok().as_deref()) {
+        return mode;
+    }
+    if let Some(mode) = osc11_response.and_then(theme_from_osc11) {
+        return mode;
+    }
+    ThemeMode::Dark
+}
+
+/// Parse a `COLORFGBG` value (`"fg;bg"`) into a theme mode using the
+/// standard xterm 16-color palette, where indices 7 and 15 are light.
+fn theme_from_colorfgbg(value: Option<&str>) -> Option<ThemeMode> {
+    let bg_index: u8 = value?.split(';').next_back()?.trim().parse().ok()?;
+    Some(if matches!(bg_index, 7 | 15) {
+        ThemeMode::Light
+    } else {
+        ThemeMode::Dark
+    })
+}
+
+/// Parse an OSC 11 background-color reply (`"rgb:rrrr/gggg/bbbb"`) into a
+/// theme mode using perceived luminance.
+fn theme_from_osc11(response: &str) -> Option<ThemeMode> {
+    let rgb = response.trim().strip_prefix("rgb:")?;
+    let mut channels = rgb.split('/');
+    let r = u16::from_str_radix(channels.next()?, 16).ok()?;
+    let g = u16::from_str_radix(channels.next()?, 16).ok()?;
+    let b = u16::from_str_radix(channels.next()?, 16).ok()?;
+    let luminance =
+        0.299 * (r >> 8) as f64 + 0.587 * (g >> 8) as f64 + 0.114 * (b >> 8) as f64;
+    Some(if luminance > 127.0 {
+        ThemeMode::Light
+    } else {
+        ThemeMode::Dark
+    })
+}
+
+/// Target color depth for terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit truecolor (16.7M colors).
+    TrueColor,
+    /// xterm 256-color palette (6x6x6 cube + grey ramp).
+    Ansi256,
+    /// Classic 16-color palette, snapped to the nearest Colodore preset.
+    Ansi16,
+}
+
+/// A color resolved and quantized to a specific [`ColorDepth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantized {
+    /// 24-bit RGB components.
+    TrueColor(u8, u8, u8),
+    /// A palette index (0-15 for `Ansi16`, 16-255 for `Ansi256`).
+    Indexed(u8),
+}
+
+/// Resolve and quantize `color` (a Colodore name or hex string) to `depth`
+/// under the given [`ThemeMode`].
+pub fn quantize(color: &str, depth: ColorDepth, mode: ThemeMode) -> Quantized {
+    let hex = resolve_color_themed(color, mode);
+    match depth {
+        ColorDepth::TrueColor => {
+            let (r, g, b) = parse_hex(&hex);
+            Quantized::TrueColor(r, g, b)
+        }
+        ColorDepth::Ansi256 => Quantized::Indexed(quantize_to_ansi256(&hex)),
+        ColorDepth::Ansi16 => Quantized::Indexed(quantize_to_ansi16(&hex)),
+    }
+}
+
+/// The 6 per-channel levels used by the xterm 256-color cube (indices 16-231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn parse_hex(hex: &str) -> (u8, u8, u8) {
+    let hex = hex.trim_start_matches('#');
+    let r = u8::from_str_radix(hex.get(0..2).unwrap_or("00"), 16).unwrap_or(0);
+    let g = u8::from_str_radix(hex.get(2..4).unwrap_or("00"), 16).unwrap_or(0);
+    let b = u8::from_str_radix(hex.get(4..6).unwrap_or("00"), 16).unwrap_or(0);
+    (r, g, b)
+}
+
+fn squared_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    dr * dr + dg * dg + db * db
+}
+
+/// Index into [`CUBE_LEVELS`] of the level nearest to `value`.
+fn nearest_cube_level(value: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &level)| (level as i32 - value as i32).abs())
+        .map(|(i, _)| i)
+        .expect("CUBE_LEVELS is non-empty")
+}
+
+/// Quantize a hex color to the xterm 256-color palette.
+///
+/// Indices 16-231 form a 6x6x6 color cube where each channel snaps to
+/// [`CUBE_LEVELS`]; indices 232-255 are a 24-step grey ramp at
+/// `8 + 10*i`. Returns whichever of the two is closer by squared RGB
+/// distance.
+pub fn quantize_to_ansi256(hex: &str) -> u8 {
+    let rgb = parse_hex(hex);
+    let (r, g, b) = rgb;
+
+    let ri = nearest_cube_level(r);
+    let gi = nearest_cube_level(g);
+    let bi = nearest_cube_level(b);
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_dist = squared_distance(rgb, cube_rgb);
+
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let grey_step = ((avg.saturating_sub(8) + 5) / 10).min(23);
+    let grey_value = (8 + 10 * grey_step) as u8;
+    let grey_index = 232 + grey_step as usize;
+    let grey_dist = squared_distance(rgb, (grey_value, grey_value, grey_value));
+
+    if grey_dist < cube_dist {
+        grey_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// Fixed Colodore name -> classic 16-color ANSI index, hand-picked to the
+/// closest standard terminal color for each preset.
+static COLODORE_ANSI16: LazyLock<HashMap<&'static str, u8>> = LazyLock::new(|| {
+    let mut m = HashMap::new();
+    m.insert("black", 0);
+    m.insert("dark_grey", 8);
+    m.insert("grey", 7);
+    m.insert("light_grey", 15);
+    m.insert("white", 15);
+    m.insert("dark_red", 1);
+    m.insert("red", 9);
+    m.insert("brown", 3);
+    m.insert("orange", 3);
+    m.insert("yellow", 11);
+    m.insert("light_green", 10);
+    m.insert("green", 2);
+    m.insert("cyan", 14);
+    m.insert("light_blue", 12);
+    m.insert("blue", 4);
+    m.insert("purple", 5);
+    m
+});
+
+/// Find the Colodore preset name nearest to an arbitrary RGB color.
+fn nearest_colodore_name(rgb: (u8, u8, u8)) -> &'static str {
+    COLODORE
+        .iter()
+        .min_by_key(|(_, hex)| squared_distance(rgb, parse_hex(hex)))
+        .map(|(name, _)| *name)
+        .expect("COLODORE is non-empty")
+}
+
+/// Quantize a hex color to the classic 16-color palette by snapping to the
+/// nearest existing (dark-palette) Colodore preset. The 16-color ANSI
+/// index table is the same regardless of theme, since it targets a
+/// terminal-managed palette rather than specific hex values.
+pub fn quantize_to_ansi16(hex: &str) -> u8 {
+    let name = nearest_colodore_name(parse_hex(hex));
+    COLODORE_ANSI16.get(name).copied().unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,4 +345,86 @@ mod tests {
         assert!(COLODORE.contains_key("white"));
         assert!(COLODORE.contains_key("purple"));
     }
+
+    #[test]
+    fn test_quantize_truecolor_passthrough() {
+        assert_eq!(
+            quantize("#123456", ColorDepth::TrueColor, ThemeMode::Dark),
+            Quantized::TrueColor(0x12, 0x34, 0x56)
+        );
+    }
+
+    #[test]
+    fn test_quantize_ansi256_cube_corners() {
+        assert_eq!(quantize_to_ansi256("#000000"), 16);
+        assert_eq!(quantize_to_ansi256("#ffffff"), 231);
+    }
+
+    #[test]
+    fn test_quantize_ansi256_grey_ramp() {
+        // A mid grey should land in the grey ramp (232-255), not the cube.
+        let idx = quantize_to_ansi256("#808080");
+        assert!((232..=255).contains(&idx));
+    }
+
+    #[test]
+    fn test_quantize_ansi16_snaps_to_colodore() {
+        // Close to Colodore's "yellow" (#edf171).
+        assert_eq!(quantize_to_ansi16("#eef272"), COLODORE_ANSI16["yellow"]);
+    }
+
+    #[test]
+    fn test_quantize_ansi16_in_range() {
+        for hex in COLODORE.values() {
+            assert!(quantize_to_ansi16(hex) < 16);
+        }
+    }
+
+    #[test]
+    fn test_resolve_color_themed_dark_matches_resolve_color() {
+        assert_eq!(resolve_color_themed("yellow", ThemeMode::Dark), "#edf171");
+    }
+
+    #[test]
+    fn test_resolve_color_themed_light_uses_light_palette() {
+        assert_eq!(resolve_color_themed("black", ThemeMode::Light), "#ffffff");
+        assert_eq!(resolve_color_themed("white", ThemeMode::Light), "#000000");
+    }
+
+    #[test]
+    fn test_resolve_color_themed_light_inverts_hex_passthrough() {
+        assert_eq!(resolve_color_themed("#000000", ThemeMode::Light), "#ffffff");
+    }
+
+    #[test]
+    fn test_colodore_light_has_all_presets() {
+        assert_eq!(COLODORE_LIGHT.len(), COLODORE.len());
+    }
+
+    #[test]
+    fn test_theme_from_colorfgbg_light() {
+        assert_eq!(theme_from_colorfgbg(Some("0;15")), Some(ThemeMode::Light));
+        assert_eq!(theme_from_colorfgbg(Some("15;0")), Some(ThemeMode::Dark));
+    }
+
+    #[test]
+    fn test_theme_from_colorfgbg_invalid() {
+        assert_eq!(theme_from_colorfgbg(Some("not-a-number")), None);
+        assert_eq!(theme_from_colorfgbg(None), None);
+    }
+
+    #[test]
+    fn test_theme_from_osc11() {
+        assert_eq!(theme_from_osc11("rgb:ffff/ffff/ffff"), Some(ThemeMode::Light));
+        assert_eq!(theme_from_osc11("rgb:0000/0000/0000"), Some(ThemeMode::Dark));
+        assert_eq!(theme_from_osc11("not-a-response"), None);
+    }
+
+    #[test]
+    fn test_detect_theme_mode_defaults_to_dark_without_hints() {
+        // No other test reads or writes COLORFGBG, so removing it here
+        // does not race with other tests running in parallel.
+        std::env::remove_var("COLORFGBG");
+        assert_eq!(detect_theme_mode(None), ThemeMode::Dark);
+    }
 }
@@ -0,0 +1,80 @@
+//! Streamdown Render
+//!
+//! Terminal rendering primitives built on top of `streamdown-ansi` escape
+//! sequences and `streamdown-syntax` highlighting: color resolution and
+//! fenced code block rendering.
+
+pub mod code;
+pub mod colors;
+
+pub use colors::{ColorDepth, Quantized, ThemeMode};
+
+/// Style configuration controlling which colors rendering uses.
+#[derive(Debug, Clone)]
+pub struct RenderStyle {
+    /// Background color for code blocks (Colodore name or hex).
+    pub dark: String,
+    /// Foreground color for borders (Colodore name or hex).
+    pub grey: String,
+    /// Foreground color for symbols/labels (Colodore name or hex).
+    pub symbol: String,
+    /// Background color for the line-number/diff gutter (Colodore name or hex).
+    pub gutter_bg: String,
+    /// Foreground color for the line-number/diff gutter (Colodore name or hex).
+    pub gutter_fg: String,
+    /// Target color depth for terminal output.
+    ///
+    /// Terminals that can't render truecolor (or users who set `NO_COLOR`/
+    /// `COLORTERM=`) need colors downsampled to 256 or 16 colors.
+    pub depth: ColorDepth,
+    /// Light or dark terminal background, selecting which Colodore
+    /// palette `dark`/`grey`/`symbol`/`gutter_bg`/`gutter_fg` resolve
+    /// against (see [`colors::detect_theme_mode`]).
+    pub theme: ThemeMode,
+}
+
+impl Default for RenderStyle {
+    fn default() -> Self {
+        Self {
+            dark: "dark_grey".to_string(),
+            grey: "grey".to_string(),
+            symbol: "light_grey".to_string(),
+            gutter_bg: "black".to_string(),
+            gutter_fg: "grey".to_string(),
+            depth: ColorDepth::TrueColor,
+            theme: ThemeMode::Dark,
+        }
+    }
+}
+
+/// Build an SGR escape sequence for `color` at the given depth and theme.
+///
+/// `base` is the SGR parameter that selects foreground (`38`) or
+/// background (`48`).
+fn sgr(base: u8, color: &str, depth: ColorDepth, theme: ThemeMode) -> String {
+    match colors::quantize(color, depth, theme) {
+        Quantized::TrueColor(r, g, b) => format!("\x1b[{base};2;{r};{g};{b}m"),
+        Quantized::Indexed(idx) if depth == ColorDepth::Ansi16 => {
+            // Ansi16 terminals don't understand the 256-color `;5;idx` form -
+            // emit the classic SGR codes instead (30-37/90-97 fg, 40-47/100-107 bg).
+            let (normal_start, bright_start) = if base == 38 { (30, 90) } else { (40, 100) };
+            let code = if idx < 8 {
+                normal_start + idx
+            } else {
+                bright_start + (idx - 8)
+            };
+            format!("\x1b[{code}m")
+        }
+        Quantized::Indexed(idx) => format!("\x1b[{base};5;{idx}m"),
+    }
+}
+
+/// Resolve `color` to a background escape sequence at the given depth and theme.
+pub fn bg_color(color: &str, depth: ColorDepth, theme: ThemeMode) -> String {
+    sgr(48, color, depth, theme)
+}
+
+/// Resolve `color` to a foreground escape sequence at the given depth and theme.
+pub fn fg_color(color: &str, depth: ColorDepth, theme: ThemeMode) -> String {
+    sgr(38, color, depth, theme)
+}